@@ -10,24 +10,28 @@
 use wapcaplet::LwcString;
 use wapcaplet::ll::{lwc_string, rust_lwc_string_ref};
 use std::libc::{c_void, c_char};
-use std::libc::types::common::c99::{uint32_t};
+use std::libc::types::common::c99::{uint32_t, uint64_t};
 use std::cast::transmute;
 use std::ptr::null;
 use ll::{c_enum, rust_enum};
 use ll_lwcstr_to_hl_lwcstr = wapcaplet::from_lwc_string;
+use lwcstr_from_rust_str = wapcaplet::from_rust_string;
 use ll::types::{css_language_level, CSS_LEVEL_1, CSS_LEVEL_2, CSS_LEVEL_21, CSS_LEVEL_3, CSS_LEVEL_DEFAULT};
 use types::{CssLanguageLevel, CssLevel1, CssLevel2, CssLevel21, CssLevel3, CssLevelDefault, CssLevelNotACLikeEnum};
-use ll::errors::{css_error, CSS_OK};
-use errors::CssError;
-use ll::properties::css_font_family_e;
-use properties::CssFontFamily;
-use ll::types::{css_color, css_unit, css_qname};
-use types::{CssColor, CssUnit, CssQName};
-use ll::stylesheet::{css_fixed, css_stylesheet_params};
-use stylesheet::{CssStylesheetParams};
+use ll::errors::{css_error, CSS_OK, CSS_INVALID};
+use errors::{CssError, CssUnknownError};
+use ll::properties::{css_font_family_e, css_font_style_e, css_font_variant_e, css_font_weight_e};
+use ll::properties::css_border_style_e;
+use properties::{CssFontFamily, CssFontStyle, CssFontVariant, CssFontWeight, CssBorderStyle};
+use ll::types::{css_color, css_unit, css_qname, css_origin};
+use types::{CssColor, CssUnit, CssQName, CssOrigin};
+use ll::stylesheet::{css_fixed, css_stylesheet_params, css_stylesheet, css_system_font, css_size};
+use stylesheet::{CssStylesheetParams, CssUrlResolutionFn, CssImportNotificationFn,
+                 CssColorResolutionFn, CssFontResolutionFn, with_ll_sheet};
 use ll::select::{css_pseudo_element};
 use select::{CssPseudoElement};
 use std::sys;
+use std::str;
 
 pub trait ToLl<T> {
     fn to_ll(&self) -> T;
@@ -50,9 +54,19 @@ impl ToLl<css_language_level> for CssLanguageLevel {
     }
 }
 
+impl ToLl<css_origin> for CssOrigin {
+    fn to_ll(&self) -> css_origin {
+        *self as css_origin
+    }
+}
+
 impl ToLl<css_error> for CssError {
     fn to_ll(&self) -> css_error {
-        *self as css_error
+        match *self {
+            // There's no code of its own to give back to libcss
+            CssUnknownError => CSS_INVALID,
+            _ => *self as css_error
+        }
     }
 }
 
@@ -62,6 +76,44 @@ impl ToLl<css_font_family_e> for CssFontFamily {
     }
 }
 
+impl ToLl<css_font_style_e> for CssFontStyle {
+    fn to_ll(&self) -> css_font_style_e {
+        *self as css_font_style_e
+    }
+}
+
+impl ToLl<css_font_variant_e> for CssFontVariant {
+    fn to_ll(&self) -> css_font_variant_e {
+        *self as css_font_variant_e
+    }
+}
+
+impl ToLl<css_font_weight_e> for CssFontWeight {
+    fn to_ll(&self) -> css_font_weight_e {
+        *self as css_font_weight_e
+    }
+}
+
+impl ToLl<css_border_style_e> for CssBorderStyle {
+    fn to_ll(&self) -> css_border_style_e {
+        use ll::properties::*;
+        use properties::*;
+        match *self {
+            CssBorderStyleInherit => CSS_BORDER_STYLE_INHERIT,
+            CssBorderStyleNone => CSS_BORDER_STYLE_NONE,
+            CssBorderStyleHidden => CSS_BORDER_STYLE_HIDDEN,
+            CssBorderStyleDotted => CSS_BORDER_STYLE_DOTTED,
+            CssBorderStyleDashed => CSS_BORDER_STYLE_DASHED,
+            CssBorderStyleSolid => CSS_BORDER_STYLE_SOLID,
+            CssBorderStyleDouble => CSS_BORDER_STYLE_DOUBLE,
+            CssBorderStyleGroove => CSS_BORDER_STYLE_GROOVE,
+            CssBorderStyleRidge => CSS_BORDER_STYLE_RIDGE,
+            CssBorderStyleInset => CSS_BORDER_STYLE_INSET,
+            CssBorderStyleOutset => CSS_BORDER_STYLE_OUTSET
+        }
+    }
+}
+
 impl ToLl<css_color> for CssColor {
     fn to_ll(&self) -> css_color {
         assert!(sys::size_of::<CssColor>() == sys::size_of::<css_color>());
@@ -100,42 +152,50 @@ impl ToLl<(css_unit, css_fixed)> for CssUnit {
 }
 
 pub fn ll_unit_to_hl_unit(unit: css_unit, value: css_fixed) -> CssUnit {
+    match maybe_ll_unit_to_hl_unit(unit, value) {
+        Some(unit) => unit,
+        None => fail!(fmt!("unknown css unit %?", unit))
+    }
+}
+
+// Returns None for units this binding doesn't know about
+pub fn maybe_ll_unit_to_hl_unit(unit: css_unit, value: css_fixed) -> Option<CssUnit> {
     use ll::types::*;
     use types::*;
     if unit == CSS_UNIT_PX {
-        CssUnitPx(value)
+        Some(CssUnitPx(value))
     } else if unit == CSS_UNIT_EX {
-        CssUnitEx(value)
+        Some(CssUnitEx(value))
     } else if unit == CSS_UNIT_EM {
-        CssUnitEm(value)
+        Some(CssUnitEm(value))
     } else if unit == CSS_UNIT_IN {
-        CssUnitIn(value)
+        Some(CssUnitIn(value))
     } else if unit == CSS_UNIT_CM {
-        CssUnitCm(value)
+        Some(CssUnitCm(value))
     } else if unit == CSS_UNIT_MM {
-        CssUnitMm(value)
+        Some(CssUnitMm(value))
     } else if unit == CSS_UNIT_PT {
-        CssUnitPt(value)
+        Some(CssUnitPt(value))
     } else if unit == CSS_UNIT_PC {
-        CssUnitPc(value)
+        Some(CssUnitPc(value))
     } else if unit == CSS_UNIT_PCT {
-        CssUnitPct(value)
+        Some(CssUnitPct(value))
     } else if unit == CSS_UNIT_DEG {
-        CssUnitDeg(value)
+        Some(CssUnitDeg(value))
     } else if unit == CSS_UNIT_GRAD {
-        CssUnitGrad(value)
+        Some(CssUnitGrad(value))
     } else if unit == CSS_UNIT_RAD {
-        CssUnitRad(value)
+        Some(CssUnitRad(value))
     } else if unit == CSS_UNIT_MS {
-        CssUnitMs(value)
+        Some(CssUnitMs(value))
     } else if unit == CSS_UNIT_S {
-        CssUnitS(value)
+        Some(CssUnitS(value))
     } else if unit == CSS_UNIT_HZ {
-        CssUnitHz(value)
+        Some(CssUnitHz(value))
     } else if unit == CSS_UNIT_KHZ {
-        CssUnitKHz(value)
+        Some(CssUnitKHz(value))
     } else {
-        fail!()
+        None
     }
 }
 
@@ -167,6 +227,16 @@ pub fn c_enum_to_rust_enum<T>(val: c_enum) -> T {
     unsafe { transmute(val as rust_enum) }
 }
 
+// For enums numbered from 0 to `last` without gaps. Returns None for
+// values past the end rather than transmuting them
+pub fn maybe_c_enum_to_rust_enum<T>(val: c_enum, last: rust_enum) -> Option<T> {
+    if val as rust_enum <= last {
+        Some(c_enum_to_rust_enum(val))
+    } else {
+        None
+    }
+}
+
 impl AsLl<css_stylesheet_params> for CssStylesheetParams {
     fn as_ll<U>(&self, f: &fn(&css_stylesheet_params) -> U) -> U {
         do self.charset.to_c_str().with_ref |charset| {
@@ -182,12 +252,21 @@ impl AsLl<css_stylesheet_params> for CssStylesheetParams {
                         inline_style: self.inline_style,
                         resolve: resolve,
                         resolve_pw: unsafe { transmute(&self.resolve) },
-                        import: null(),
-                        import_pw: null(),
-                        color: null(),
-                        color_pw: null(),
-                        font: null(),
-                        font_pw: null()
+                        import: match self.import {
+                            Some(_) => import_notification,
+                            None => null()
+                        },
+                        import_pw: unsafe { transmute(&self.import) },
+                        color: match self.color {
+                            Some(_) => color_resolution,
+                            None => null()
+                        },
+                        color_pw: unsafe { transmute(&self.color) },
+                        font: match self.font {
+                            Some(_) => font_resolution,
+                            None => null()
+                        },
+                        font_pw: unsafe { transmute(&self.font) }
                     };
                     f(&params)
                 }
@@ -196,23 +275,110 @@ impl AsLl<css_stylesheet_params> for CssStylesheetParams {
     }
 }
 
-extern fn resolve(_pw: *c_void, _base: *c_char, rel: *lwc_string, abs: *mut *lwc_string) -> css_error {
-    unsafe {
-        // TODO
-        rust_lwc_string_ref(rel);
-        *abs = rel;
+extern fn resolve(pw: *c_void, base: *c_char, rel: *lwc_string, abs: *mut *lwc_string) -> css_error {
+    let resolve_fn: &Option<CssUrlResolutionFn> = unsafe { transmute(pw) };
+    match *resolve_fn {
+        Some(ref resolve_fn) => {
+            let base = unsafe { str::raw::from_c_str(base) };
+            let hlrel = ll_lwcstr_to_hl_lwcstr(rel);
+            match (*resolve_fn)(base.as_slice(), &hlrel) {
+                Ok(hlabs) => {
+                    unsafe { *abs = hlabs.raw_reffed(); }
+                    CSS_OK
+                }
+                Err(e) => e.to_ll()
+            }
+        }
+        None => {
+            // Nothing to resolve against, so treat the url as already absolute
+            unsafe {
+                rust_lwc_string_ref(rel);
+                *abs = rel;
+            }
+            CSS_OK
+        }
     }
-    CSS_OK
 }
 
-pub fn write_ll_qname(hlqname: &mut CssQName, llqname: *mut css_qname) {
-    unsafe {
-        match &hlqname.ns {
-            &Some(ref ns) => {
-                (*llqname).ns = ns.raw_reffed();
+extern fn import_notification(pw: *c_void, parent: *css_stylesheet, url: *lwc_string, media: *mut uint64_t) -> css_error {
+    let import_fn: &Option<CssImportNotificationFn> = unsafe { transmute(pw) };
+    match *import_fn {
+        Some(ref import_fn) => {
+            let hlurl = ll_lwcstr_to_hl_lwcstr(url);
+            do with_ll_sheet(parent) |hlparent| {
+                match (*import_fn)(hlparent, &hlurl) {
+                    Ok(hlmedia) => {
+                        unsafe { *media = hlmedia.to_ll(); }
+                        CSS_OK
+                    }
+                    Err(e) => e.to_ll()
+                }
+            }
+        }
+        None => CSS_OK
+    }
+}
+
+extern fn color_resolution(pw: *c_void, name: *lwc_string, color: *mut css_color) -> css_error {
+    let color_fn: &Option<CssColorResolutionFn> = unsafe { transmute(pw) };
+    match *color_fn {
+        Some(ref color_fn) => {
+            let hlname = ll_lwcstr_to_hl_lwcstr(name);
+            match (*color_fn)(&hlname) {
+                Ok(hlcolor) => {
+                    unsafe { *color = hlcolor.to_ll(); }
+                    CSS_OK
+                }
+                Err(e) => e.to_ll()
             }
-            _ => ()
         }
+        // libcss treats this as an unknown colour name
+        None => CSS_INVALID
+    }
+}
+
+extern fn font_resolution(pw: *c_void, name: *lwc_string, system_font: *mut css_system_font) -> css_error {
+    let font_fn: &Option<CssFontResolutionFn> = unsafe { transmute(pw) };
+    match *font_fn {
+        Some(ref font_fn) => {
+            let hlname = ll_lwcstr_to_hl_lwcstr(name);
+            match (*font_fn)(&hlname) {
+                Ok(hlfont) => {
+                    let (size_unit, size) = hlfont.size.to_ll();
+                    let (line_height_unit, line_height) = hlfont.line_height.to_ll();
+                    unsafe {
+                        *system_font = css_system_font {
+                            style: hlfont.style.to_ll(),
+                            variant: hlfont.variant.to_ll(),
+                            weight: hlfont.weight.to_ll(),
+                            size: css_size {
+                                size: size,
+                                unit: size_unit
+                            },
+                            line_height: css_size {
+                                size: line_height,
+                                unit: line_height_unit
+                            },
+                            // libcss takes ownership of this reference
+                            family: lwcstr_from_rust_str(hlfont.family.as_slice()).raw_reffed()
+                        };
+                    }
+                    CSS_OK
+                }
+                Err(e) => e.to_ll()
+            }
+        }
+        // libcss treats this as an unknown system font
+        None => CSS_INVALID
+    }
+}
+
+pub fn write_ll_qname(hlqname: &mut CssQName, llqname: *mut css_qname) {
+    unsafe {
+        (*llqname).ns = match &hlqname.ns {
+            &Some(ref ns) => ns.raw_reffed(),
+            &None => null()
+        };
         (*llqname).name = hlqname.name.raw_reffed();
     }
 }
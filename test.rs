@@ -10,13 +10,17 @@
 // Based off of libcss's examples/example1.c
 mod example1 {
 
-    use CssResult;
     use CssProperty;
+    use errors::{CssError, CssInvalid};
     use types::*;
     use hint::*;
     use select::*;
-    use util::VoidPtrLike;
-    use wapcaplet::LwcString;
+    use properties::{CssPropBackgroundColor, CssPropBorderTopWidth, CssPropBorderTopStyle, CssPropWidth};
+    use properties::CssBorderStyleOutset;
+    use stylesheet::{CssStylesheet, CssStylesheetParams, CssStylesheetParamsBuilder, css_stylesheet_create};
+    use values::{CssColorColor, CssColorInherit};
+    use util::{VoidPtrLike, qname_matches, net_qname_to_rust_str, float_to_css_fixed};
+    use wapcaplet::{LwcString, from_rust_string};
     use std::libc;
     use std::cast;
 
@@ -43,13 +47,13 @@ mod example1 {
 
     #[test]
     fn run() {
-        use super::super::stylesheet::{CssUrlResolutionFn, CssStylesheetParams, CssStylesheet,
+        use super::super::stylesheet::{CssUrlResolutionFn, CssStylesheetParams,
                                        css_stylesheet_create, CssStylesheetParamsVersion1};
+        use super::super::stylesheet::{CssParseNeedData, CssParseDone};
         use super::super::computed::CssComputedStyle;
-        use super::super::values::{CssColorColor, CssColorInherit};
-        use super::super::ll::types::{CSS_ORIGIN_AUTHOR, CSS_MEDIA_ALL, CSS_MEDIA_SCREEN};
+        use super::super::values::{CssComputedColor, CssComputedUnsupported, CssComputedUnknown};
+        use super::super::properties::{CssPropColor, CssPropAzimuth, all_properties};
         use super::super::conversions::ToLl;
-        use wapcaplet::{LwcString, from_rust_string};
 
         let data = "h1 { color: red; }\
                     h4 { color: #321; }\
@@ -71,20 +75,31 @@ mod example1 {
             font: None,
         };
 
-        let mut sheet: CssStylesheet = css_stylesheet_create(&params);
+        let mut sheet: CssStylesheet = css_stylesheet_create(params);
         debug!("stylesheet: %?", sheet);
         debug!("stylesheet size: %?", sheet.size());
 
-        sheet.append_data(data.as_bytes().to_owned());
-        sheet.data_done();
+        match sheet.append_data(data.as_bytes().to_owned()) {
+            Ok(CssParseNeedData) => (),
+            Ok(status) => fail!(fmt!("unexpected parse status: %?", status)),
+            Err(e) => fail!(e.to_str())
+        }
+        match sheet.data_done() {
+            Ok(CssParseDone) => (),
+            Ok(status) => fail!(fmt!("unexpected parse status: %?", status)),
+            Err(e) => fail!(e.to_str())
+        }
         debug!("stylesheet size: %?", sheet.size());
 
 
         let mut select_ctx: CssSelectCtx = css_select_ctx_create();
         assert!(select_ctx.count_sheets() == 0);
-        select_ctx.append_sheet(sheet, CSS_ORIGIN_AUTHOR, CSS_MEDIA_ALL);
+        select_ctx.append_sheet(sheet, CssOriginAuthor, CssMediaAll);
         debug!("count sheets: %?", select_ctx.count_sheets());
         assert!(select_ctx.count_sheets() == 1);
+        assert!(select_ctx.sheet_origin(0) == CssOriginAuthor);
+        assert!(select_ctx.sheet_media(0) == CssMediaAll);
+        assert!(select_ctx.sheet(0).url() == Some("foo"));
 
         for hh in range(1u, 7u) {
             let element = fmt!("h%u", hh);
@@ -92,11 +107,33 @@ mod example1 {
             let node = MyDomNode { name: element_name };
             let select_handler = SelectHandler { bogus: () };
             let style: CssSelectResults = select_ctx.select_style(&node,
-                                                                  CSS_MEDIA_SCREEN,
+                                                                  CssMediaScreen,
                                                                   None,
                                                                   &select_handler);
 
             let computed: CssComputedStyle = style.computed_style(CssPseudoElementNone);
+            assert!(style.pseudo_element_style(CssPseudoElementBefore).is_none());
+            // Only the element itself has a style, there are no pseudo element rules
+            let mut elements = ~[];
+            for (element, _) in style.pseudo_elements() {
+                elements.push(element as uint);
+            }
+            assert!(elements == ~[CssPseudoElementNone as uint]);
+
+            match computed.get(CssPropColor) {
+                CssComputedColor(_) => (),
+                _ => fail!(~"color should have a computed value")
+            }
+            match computed.get(CssPropAzimuth) {
+                CssComputedUnsupported => (),
+                _ => fail!(~"azimuth isn't computed")
+            }
+            for property in all_properties() {
+                match computed.get(property) {
+                    CssComputedUnknown => fail!(fmt!("unknown value for %?", property)),
+                    _ => ()
+                }
+            }
 
             match computed.color() {
                 CssColorInherit => {
@@ -109,8 +146,10 @@ mod example1 {
         }
     }
 
-    fn resolve_url(_base: &str, _rel: &LwcString) -> CssResult<LwcString> {
-        fail!(~"resolving url");
+    // The sheet has no urls, so this is never called. If it were, failing
+    // here would unwind into libcss
+    fn resolve_url(_base: &str, _rel: &LwcString) -> Result<LwcString, CssError> {
+        Err(CssInvalid)
     }
 
     struct SelectHandler {
@@ -139,27 +178,726 @@ mod example1 {
         fn parent_node(&self, _node: &MyDomNode) -> Option<MyDomNode> {
             None
         }
+
+        fn sibling_node(&self, _node: &MyDomNode) -> Option<MyDomNode> {
+            None
+        }
+
+        fn named_sibling_node(&self, _node: &MyDomNode, _qname: &CssQName) -> Option<MyDomNode> {
+            None
+        }
+
+        fn named_generic_sibling_node(&self, _node: &MyDomNode, _qname: &CssQName) -> Option<MyDomNode> {
+            None
+        }
         
         fn node_has_class(&self, _node: &MyDomNode, _name: LwcString) -> bool { false }
 
         fn node_has_id(&self, _node: &MyDomNode, _name: LwcString) -> bool { false }
 
+        fn node_attribute(&self, _node: &MyDomNode, _qname: &CssQName) -> Option<~str> { None }
+
         fn named_ancestor_node(&self, _node: &MyDomNode, _qname: &CssQName) -> Option<MyDomNode> {
             None
         }
 
         fn node_is_root(&self, _node: &MyDomNode) -> bool { false }
 
+        fn node_count_siblings(&self, _node: &MyDomNode, _same_name: bool, _after: bool) -> int { 0 }
+
+        fn node_is_empty(&self, _node: &MyDomNode) -> bool { true }
+
         fn node_is_link(&self, _node: &MyDomNode) -> bool { false }
 
         fn node_is_visited(&self, _node: &MyDomNode) -> bool { false }
 
+        fn node_is_hover(&self, _node: &MyDomNode) -> bool { false }
+
+        fn node_is_active(&self, _node: &MyDomNode) -> bool { false }
+
+        fn node_is_focus(&self, _node: &MyDomNode) -> bool { false }
+
+        fn node_is_enabled(&self, _node: &MyDomNode) -> bool { true }
+
+        fn node_is_disabled(&self, _node: &MyDomNode) -> bool { false }
+
+        fn node_is_checked(&self, _node: &MyDomNode) -> bool { false }
+
+        fn node_is_target(&self, _node: &MyDomNode) -> bool { false }
+
+        fn node_language(&self, _node: &MyDomNode) -> Option<~str> { None }
+
+        fn node_presentational_hint(&self, _node: &MyDomNode, _property: CssProperty) -> Option<CssHint> {
+            None
+        }
+
         fn ua_default_for_property(&self, property: CssProperty) -> CssHint {
             match property {
                 _ => CssHintDefault
             }
         }
     }
+
+    // A small document tree, for selectors that look at parents and siblings.
+    // Elements are children of their parent in the order they were added.
+    // bgcolor and border stand in for the presentational attributes, and
+    // attrs holds the others, apart from class and id
+    struct MyElement {
+        // A namespace URI, None for elements without a namespace
+        ns: Option<~str>,
+        name: ~str,
+        classes: ~[~str],
+        id: Option<~str>,
+        attrs: ~[(~str, ~str)],
+        parent: Option<uint>,
+        hover: bool,
+        bgcolor: Option<CssColor>,
+        border: Option<uint>
+    }
+
+    struct MyTree {
+        elements: ~[MyElement]
+    }
+
+    // An index into MyTree.elements. libcss gets the index plus one, so that
+    // the first element isn't null
+    struct MyTreeNode {
+        index: uint
+    }
+
+    impl VoidPtrLike for MyTreeNode {
+        fn from_void_ptr(node: *libc::c_void) -> MyTreeNode {
+            assert!(node.is_not_null());
+            let index: uint = unsafe { cast::transmute(node) };
+            MyTreeNode { index: index - 1 }
+        }
+
+        fn to_void_ptr(&self) -> *libc::c_void {
+            unsafe { cast::transmute(self.index + 1) }
+        }
+    }
+
+    impl MyTree {
+        fn new() -> MyTree {
+            MyTree { elements: ~[] }
+        }
+
+        fn add(&mut self, parent: Option<MyTreeNode>, name: &str) -> MyTreeNode {
+            self.elements.push(MyElement {
+                ns: None,
+                name: name.to_owned(),
+                classes: ~[],
+                id: None,
+                attrs: ~[],
+                parent: match parent {
+                    Some(parent) => Some(parent.index),
+                    None => None
+                },
+                hover: false,
+                bgcolor: None,
+                border: None
+            });
+            MyTreeNode { index: self.elements.len() - 1 }
+        }
+
+        // The sibling elements before the node (or, if `after`, following
+        // it), in document order
+        fn siblings(&self, node: &MyTreeNode, after: bool) -> ~[MyTreeNode] {
+            let parent = self.elements[node.index].parent;
+            let mut siblings = ~[];
+            for (index, element) in self.elements.iter().enumerate() {
+                let placed = if after { index > node.index } else { index < node.index };
+                if placed && element.parent == parent {
+                    siblings.push(MyTreeNode { index: index });
+                }
+            }
+            siblings
+        }
+
+        fn has_name(&self, node: &MyTreeNode, qname: &CssQName) -> bool {
+            qname_matches(&self.node_name(node), qname)
+        }
+    }
+
+    impl CssSelectHandler<MyTreeNode> for MyTree {
+        fn node_name(&self, node: &MyTreeNode) -> CssQName {
+            let element = &self.elements[node.index];
+            CssQName {
+                ns: match element.ns {
+                    Some(ref ns) => Some(from_rust_string(ns.as_slice())),
+                    None => None
+                },
+                name: from_rust_string(element.name.as_slice())
+            }
+        }
+
+        fn node_classes(&self, node: &MyTreeNode) -> Option<~[LwcString]> {
+            let classes = &self.elements[node.index].classes;
+            if classes.is_empty() {
+                None
+            } else {
+                Some(classes.map(|class| from_rust_string(class.as_slice())))
+            }
+        }
+
+        fn node_id(&self, node: &MyTreeNode) -> Option<LwcString> {
+            match self.elements[node.index].id {
+                Some(ref id) => Some(from_rust_string(id.as_slice())),
+                None => None
+            }
+        }
+
+        fn named_parent_node(&self, node: &MyTreeNode, qname: &CssQName) -> Option<MyTreeNode> {
+            match self.parent_node(node) {
+                Some(parent) => if self.has_name(&parent, qname) { Some(parent) } else { None },
+                None => None
+            }
+        }
+
+        fn parent_node(&self, node: &MyTreeNode) -> Option<MyTreeNode> {
+            match self.elements[node.index].parent {
+                Some(index) => Some(MyTreeNode { index: index }),
+                None => None
+            }
+        }
+
+        fn sibling_node(&self, node: &MyTreeNode) -> Option<MyTreeNode> {
+            let mut siblings = self.siblings(node, false);
+            if siblings.is_empty() { None } else { Some(siblings.pop()) }
+        }
+
+        fn named_sibling_node(&self, node: &MyTreeNode, qname: &CssQName) -> Option<MyTreeNode> {
+            match self.sibling_node(node) {
+                Some(sibling) => if self.has_name(&sibling, qname) { Some(sibling) } else { None },
+                None => None
+            }
+        }
+
+        fn named_generic_sibling_node(&self, node: &MyTreeNode, qname: &CssQName) -> Option<MyTreeNode> {
+            let mut siblings = self.siblings(node, false);
+            while !siblings.is_empty() {
+                let sibling = siblings.pop();
+                if self.has_name(&sibling, qname) {
+                    return Some(sibling);
+                }
+            }
+            None
+        }
+
+        fn node_has_class(&self, node: &MyTreeNode, name: LwcString) -> bool {
+            self.elements[node.index].classes.iter().any(|class| class.as_slice() == name.to_str_slice())
+        }
+
+        fn node_has_id(&self, node: &MyTreeNode, name: LwcString) -> bool {
+            match self.elements[node.index].id {
+                Some(ref id) => id.as_slice() == name.to_str_slice(),
+                None => false
+            }
+        }
+
+        fn node_attribute(&self, node: &MyTreeNode, qname: &CssQName) -> Option<~str> {
+            let element = &self.elements[node.index];
+            let name = net_qname_to_rust_str(qname);
+            match name {
+                "class" if !element.classes.is_empty() => return Some(element.classes.connect(" ")),
+                "id" => return element.id.clone(),
+                _ => ()
+            }
+            for &(ref attr, ref value) in element.attrs.iter() {
+                if attr.as_slice() == name {
+                    return Some(value.clone());
+                }
+            }
+            None
+        }
+
+        fn named_ancestor_node(&self, node: &MyTreeNode, qname: &CssQName) -> Option<MyTreeNode> {
+            let mut ancestor = self.parent_node(node);
+            loop {
+                match ancestor {
+                    Some(parent) => {
+                        if self.has_name(&parent, qname) {
+                            return Some(parent);
+                        }
+                        ancestor = self.parent_node(&parent);
+                    }
+                    None => return None
+                }
+            }
+        }
+
+        fn node_is_root(&self, node: &MyTreeNode) -> bool {
+            self.elements[node.index].parent.is_none()
+        }
+
+        fn node_count_siblings(&self, node: &MyTreeNode, same_name: bool, after: bool) -> int {
+            let name = self.elements[node.index].name.as_slice();
+            let mut count = 0;
+            for sibling in self.siblings(node, after).iter() {
+                if !same_name || self.elements[sibling.index].name.as_slice() == name {
+                    count += 1;
+                }
+            }
+            count
+        }
+
+        fn node_is_empty(&self, node: &MyTreeNode) -> bool {
+            !self.elements.iter().any(|element| element.parent == Some(node.index))
+        }
+
+        fn node_is_link(&self, _node: &MyTreeNode) -> bool { false }
+
+        fn node_is_visited(&self, _node: &MyTreeNode) -> bool { false }
+
+        fn node_is_hover(&self, node: &MyTreeNode) -> bool {
+            self.elements[node.index].hover
+        }
+
+        fn node_is_active(&self, _node: &MyTreeNode) -> bool { false }
+
+        fn node_is_focus(&self, _node: &MyTreeNode) -> bool { false }
+
+        fn node_is_enabled(&self, _node: &MyTreeNode) -> bool { true }
+
+        fn node_is_disabled(&self, _node: &MyTreeNode) -> bool { false }
+
+        fn node_is_checked(&self, _node: &MyTreeNode) -> bool { false }
+
+        fn node_is_target(&self, _node: &MyTreeNode) -> bool { false }
+
+        // From the nearest lang attribute
+        fn node_language(&self, node: &MyTreeNode) -> Option<~str> {
+            let lang = CssQName { ns: None, name: from_rust_string("lang") };
+            let mut current = Some(*node);
+            loop {
+                match current {
+                    Some(element) => {
+                        match self.node_attribute(&element, &lang) {
+                            Some(value) => return Some(value),
+                            None => current = self.parent_node(&element)
+                        }
+                    }
+                    None => return None
+                }
+            }
+        }
+
+        fn node_presentational_hint(&self, node: &MyTreeNode, property: CssProperty) -> Option<CssHint> {
+            let element = &self.elements[node.index];
+            match (property, element.bgcolor, element.border) {
+                (CssPropBackgroundColor, Some(color), _) => Some(CssHintColor(color)),
+                // Doesn't apply to width, so should be ignored
+                (CssPropWidth, Some(color), _) => Some(CssHintColor(color)),
+                (CssPropBorderTopWidth, _, Some(width)) => {
+                    Some(CssHintLength(CssUnitPx(float_to_css_fixed(width as float))))
+                }
+                (CssPropBorderTopStyle, _, Some(_)) => Some(CssHintBorderStyle(CssBorderStyleOutset)),
+                _ => None
+            }
+        }
+
+        fn ua_default_for_property(&self, _property: CssProperty) -> CssHint {
+            CssHintDefault
+        }
+    }
+
+    fn author_select_ctx(css: &str, quirks: bool) -> CssSelectCtx {
+        let sheet = CssStylesheet::from_str_with_quirks("author", css, quirks).unwrap();
+        let mut select_ctx = css_select_ctx_create();
+        select_ctx.set_quirks_mode(quirks);
+        select_ctx.append_sheet(sheet, CssOriginAuthor, CssMediaAll);
+        select_ctx
+    }
+
+    fn params_select_ctx(params: CssStylesheetParams, css: &str) -> CssSelectCtx {
+        let mut sheet = css_stylesheet_create(params);
+        assert!(sheet.append_data(css.as_bytes()).is_ok());
+        assert!(sheet.data_done().is_ok());
+        let mut select_ctx = css_select_ctx_create();
+        select_ctx.append_sheet(sheet, CssOriginAuthor, CssMediaAll);
+        select_ctx
+    }
+
+    // The node's colour as (r, g, b), or None if it is left to inherit
+    fn select_color(select_ctx: &CssSelectCtx, tree: &MyTree, node: MyTreeNode,
+                    inline_style: Option<&CssStylesheet>) -> Option<(u8, u8, u8)> {
+        let results = select_ctx.select_style(&node, CssMediaScreen, inline_style, tree);
+        match results.computed_style(CssPseudoElementNone).color() {
+            CssColorColor(color) => Some((color.r, color.g, color.b)),
+            CssColorInherit => None
+        }
+    }
+
+    #[test]
+    fn color_resolution() {
+        use std::ascii::StrAsciiExt;
+
+        let mut tree = MyTree::new();
+        let html = tree.add(None, "html");
+        let p = tree.add(Some(html), "p");
+
+        let params = CssStylesheetParamsBuilder::new().url("author").color(|name| {
+            if name.to_str_slice().eq_ignore_ascii_case("ButtonFace") {
+                Ok(CssColor { r: 0xd4, g: 0xd0, b: 0xc8, a: 0xff })
+            } else {
+                Err(CssInvalid)
+            }
+        }).build();
+        let select_ctx = params_select_ctx(params, "p { color: ButtonFace }");
+        assert!(select_color(&select_ctx, &tree, p, None) == Some((0xd4, 0xd0, 0xc8)));
+    }
+
+    #[test]
+    fn font_resolution() {
+        use std::ascii::StrAsciiExt;
+        use stylesheet::CssSystemFont;
+        use properties::{CssFontStyleItalic, CssFontVariantNormal, CssFontWeightBold};
+        use values::{CssFontSizeDimension, CssFontFamilyValue};
+        use util::css_fixed_to_float;
+
+        let mut tree = MyTree::new();
+        let html = tree.add(None, "html");
+        let p = tree.add(Some(html), "p");
+
+        let params = CssStylesheetParamsBuilder::new().url("author").font(|name| {
+            if name.to_str_slice().eq_ignore_ascii_case("caption") {
+                Ok(CssSystemFont {
+                    style: CssFontStyleItalic,
+                    variant: CssFontVariantNormal,
+                    weight: CssFontWeightBold,
+                    size: CssUnitPx(float_to_css_fixed(13.0)),
+                    line_height: CssUnitPx(float_to_css_fixed(16.0)),
+                    family: ~"Chicago"
+                })
+            } else {
+                Err(CssInvalid)
+            }
+        }).build();
+        let select_ctx = params_select_ctx(params, "p { font: caption }");
+        let results = select_ctx.select_style(&p, CssMediaScreen, None, &tree);
+        let style = results.computed_style(CssPseudoElementNone);
+
+        match style.font_size() {
+            CssFontSizeDimension(CssUnitPx(size)) => assert!(css_fixed_to_float(size) == 13.0),
+            _ => fail!(~"font-size not taken from the system font")
+        }
+        // The computed value enums share css_font_style_e and css_font_weight_e
+        // with the ones in CssSystemFont
+        assert!(style.font_style() as uint == CssFontStyleItalic as uint);
+        assert!(style.font_weight() as uint == CssFontWeightBold as uint);
+        match style.font_family() {
+            CssFontFamilyValue(names) => {
+                assert!(names.len() == 1);
+                assert!(names[0].to_str_slice() == "Chicago");
+            }
+            _ => fail!(~"font-family not taken from the system font")
+        }
+    }
+
+    #[test]
+    fn user_agent_sheets() {
+        use ua::{UserAgentSheets, html_select_ctx};
+        use values::{CssDisplayBlock, CssFontWeightBold, CssTextAlignLeft};
+
+        let mut tree = MyTree::new();
+        let html = tree.add(None, "html");
+        let body = tree.add(Some(html), "body");
+        let h1 = tree.add(Some(body), "h1");
+        let p = tree.add(Some(body), "p");
+        let table = tree.add(Some(body), "table");
+
+        let sheets = UserAgentSheets::new();
+        let standards = html_select_ctx(&sheets, false);
+        let quirks = html_select_ctx(&sheets, true);
+        for select_ctx in [&standards, &quirks].iter() {
+            let results = select_ctx.select_style(&p, CssMediaScreen, None, &tree);
+            let display = results.computed_style(CssPseudoElementNone).display(false);
+            assert!(display as uint == CssDisplayBlock as uint);
+
+            let results = select_ctx.select_style(&h1, CssMediaScreen, None, &tree);
+            let weight = results.computed_style(CssPseudoElementNone).font_weight();
+            assert!(weight as uint == CssFontWeightBold as uint);
+        }
+
+        // From quirks.css
+        let results = quirks.select_style(&table, CssMediaScreen, None, &tree);
+        let align = results.computed_style(CssPseudoElementNone).text_align();
+        assert!(align as uint == CssTextAlignLeft as uint);
+        let results = standards.select_style(&table, CssMediaScreen, None, &tree);
+        let align = results.computed_style(CssPseudoElementNone).text_align();
+        assert!(align as uint != CssTextAlignLeft as uint);
+    }
+
+    #[test]
+    fn attribute_selectors() {
+        let mut tree = MyTree::new();
+        let html = tree.add(None, "html");
+        let text = tree.add(Some(html), "input");
+        tree.elements[text.index].attrs = ~[(~"type", ~"text")];
+        let checkbox = tree.add(Some(html), "input");
+        tree.elements[checkbox.index].attrs = ~[(~"type", ~"checkbox")];
+        let external = tree.add(Some(html), "a");
+        tree.elements[external.index].attrs = ~[(~"href", ~"http://example.com/")];
+        let local = tree.add(Some(html), "a");
+        tree.elements[local.index].attrs = ~[(~"href", ~"/index.html")];
+        let english = tree.add(Some(html), "p");
+        tree.elements[english.index].attrs = ~[(~"lang", ~"en-US")];
+        let english_ish = tree.add(Some(html), "p");
+        tree.elements[english_ish.index].attrs = ~[(~"lang", ~"eng")];
+        let x = tree.add(Some(html), "span");
+        tree.elements[x.index].classes = ~[~"x", ~"y"];
+        let xy = tree.add(Some(html), "span");
+        tree.elements[xy.index].classes = ~[~"xy"];
+
+        let select_ctx = author_select_ctx("input[type=text] { color: #ff0000 } \
+                                            a[href^=\"http\"] { color: #00ff00 } \
+                                            [lang|=en] { color: #0000ff } \
+                                            [class~=x] { color: #ffff00 }", false);
+        assert!(select_color(&select_ctx, &tree, text, None) == Some((255, 0, 0)));
+        assert!(select_color(&select_ctx, &tree, checkbox, None) == None);
+        assert!(select_color(&select_ctx, &tree, external, None) == Some((0, 255, 0)));
+        assert!(select_color(&select_ctx, &tree, local, None) == None);
+        assert!(select_color(&select_ctx, &tree, english, None) == Some((0, 0, 255)));
+        assert!(select_color(&select_ctx, &tree, english_ish, None) == None);
+        assert!(select_color(&select_ctx, &tree, x, None) == Some((255, 255, 0)));
+        assert!(select_color(&select_ctx, &tree, xy, None) == None);
+    }
+
+    #[test]
+    fn lang_pseudo_class() {
+        let mut tree = MyTree::new();
+        let html = tree.add(None, "html");
+        let english = tree.add(Some(html), "div");
+        tree.elements[english.index].attrs = ~[(~"lang", ~"en-US")];
+        let inherited = tree.add(Some(english), "p");
+        let french = tree.add(Some(html), "p");
+        tree.elements[french.index].attrs = ~[(~"lang", ~"fr")];
+
+        let select_ctx = author_select_ctx(":lang(en) { color: #ff0000 }", false);
+        assert!(select_color(&select_ctx, &tree, english, None) == Some((255, 0, 0)));
+        assert!(select_color(&select_ctx, &tree, inherited, None) == Some((255, 0, 0)));
+        assert!(select_color(&select_ctx, &tree, french, None) == None);
+    }
+
+    #[test]
+    fn namespaced_type_selectors() {
+        let mut tree = MyTree::new();
+        let html = tree.add(None, "html");
+        tree.elements[html.index].ns = Some(HTML_NAMESPACE.to_owned());
+        let svg_rect = tree.add(Some(html), "rect");
+        tree.elements[svg_rect.index].ns = Some(SVG_NAMESPACE.to_owned());
+        let plain_rect = tree.add(Some(html), "rect");
+
+        let red = Some((255u8, 0u8, 0u8));
+        let ns_select_ctx = |selector: &str| {
+            let css = fmt!("@namespace svg url(%s); %s { color: #ff0000 }", SVG_NAMESPACE, selector);
+            author_select_ctx(css, false)
+        };
+
+        let select_ctx = ns_select_ctx("svg|rect");
+        assert!(select_color(&select_ctx, &tree, svg_rect, None) == red);
+        assert!(select_color(&select_ctx, &tree, plain_rect, None) == None);
+
+        // Only elements without a namespace
+        let select_ctx = ns_select_ctx("|rect");
+        assert!(select_color(&select_ctx, &tree, svg_rect, None) == None);
+        assert!(select_color(&select_ctx, &tree, plain_rect, None) == red);
+
+        // Any namespace, as is a type selector without a default namespace
+        let select_ctx = ns_select_ctx("*|rect");
+        assert!(select_color(&select_ctx, &tree, svg_rect, None) == red);
+        assert!(select_color(&select_ctx, &tree, plain_rect, None) == red);
+        let select_ctx = ns_select_ctx("rect");
+        assert!(select_color(&select_ctx, &tree, svg_rect, None) == red);
+        assert!(select_color(&select_ctx, &tree, plain_rect, None) == red);
+    }
+
+    #[test]
+    fn before_pseudo_element() {
+        let mut tree = MyTree::new();
+        let html = tree.add(None, "html");
+        let p = tree.add(Some(html), "p");
+
+        let select_ctx = author_select_ctx("p:before { content: \"x\"; color: #ff0000 }", false);
+        let results = select_ctx.select_style(&p, CssMediaScreen, None, &tree);
+
+        let mut elements = ~[];
+        for (element, _) in results.pseudo_elements() {
+            elements.push(element as uint);
+        }
+        assert!(elements == ~[CssPseudoElementNone as uint, CssPseudoElementBefore as uint]);
+
+        match results.pseudo_element_style(CssPseudoElementBefore) {
+            Some(style) => match style.color() {
+                CssColorColor(color) => assert!((color.r, color.g, color.b) == (255, 0, 0)),
+                CssColorInherit => fail!(~":before should have its own colour")
+            },
+            None => fail!(~":before should have a style")
+        }
+        assert!(results.pseudo_element_style(CssPseudoElementAfter).is_none());
+    }
+
+    #[test]
+    fn sibling_combinators() {
+        let mut tree = MyTree::new();
+        let html = tree.add(None, "html");
+        let body = tree.add(Some(html), "body");
+        let h1 = tree.add(Some(body), "h1");
+        let p1 = tree.add(Some(body), "p");
+        let h2 = tree.add(Some(body), "h2");
+        let span = tree.add(Some(body), "span");
+        let p2 = tree.add(Some(body), "p");
+
+        let select_ctx = author_select_ctx("h1 + p { color: #ff0000 } h2 ~ p { color: #0000ff }", false);
+        assert!(select_color(&select_ctx, &tree, p1, None) == Some((255, 0, 0)));
+        assert!(select_color(&select_ctx, &tree, p2, None) == Some((0, 0, 255)));
+        assert!(select_color(&select_ctx, &tree, h1, None) == None);
+        assert!(select_color(&select_ctx, &tree, h2, None) == None);
+        assert!(select_color(&select_ctx, &tree, span, None) == None);
+    }
+
+    #[test]
+    fn structural_pseudo_classes() {
+        let mut tree = MyTree::new();
+        let html = tree.add(None, "html");
+        let ul = tree.add(Some(html), "ul");
+        let first = tree.add(Some(ul), "li");
+        let middle = tree.add(Some(ul), "li");
+        let last = tree.add(Some(ul), "li");
+        let empty = tree.add(Some(html), "p");
+        let full = tree.add(Some(html), "p");
+        tree.add(Some(full), "span");
+
+        let select_ctx = author_select_ctx("li:first-child { color: #ff0000 } \
+                                            li:last-child { color: #0000ff } \
+                                            p:empty { color: #00ff00 }", false);
+        assert!(select_color(&select_ctx, &tree, first, None) == Some((255, 0, 0)));
+        assert!(select_color(&select_ctx, &tree, middle, None) == None);
+        assert!(select_color(&select_ctx, &tree, last, None) == Some((0, 0, 255)));
+        assert!(select_color(&select_ctx, &tree, empty, None) == Some((0, 255, 0)));
+        assert!(select_color(&select_ctx, &tree, full, None) == None);
+    }
+
+    #[test]
+    fn dynamic_pseudo_classes() {
+        let mut tree = MyTree::new();
+        let html = tree.add(None, "html");
+        let a = tree.add(Some(html), "a");
+
+        let select_ctx = author_select_ctx("a:hover { color: #ff0000 }", false);
+        assert!(select_color(&select_ctx, &tree, a, None) == None);
+        tree.elements[a.index].hover = true;
+        assert!(select_color(&select_ctx, &tree, a, None) == Some((255, 0, 0)));
+    }
+
+    #[test]
+    fn presentational_hints() {
+        use values::CssBorderWidthWidth;
+        use util::css_fixed_to_float;
+
+        let mut tree = MyTree::new();
+        let html = tree.add(None, "html");
+        let table = tree.add(Some(html), "table");
+        tree.elements[table.index].bgcolor = Some(CssColor { r: 255, g: 0, b: 0, a: 255 });
+        tree.elements[table.index].border = Some(2);
+
+        let select_ctx = author_select_ctx("", false);
+        let results = select_ctx.select_style(&table, CssMediaScreen, None, &tree);
+        let style = results.computed_style(CssPseudoElementNone);
+        match style.background_color() {
+            CssColorColor(color) => assert!((color.r, color.g, color.b) == (255, 0, 0)),
+            CssColorInherit => fail!(~"background-color hint not applied")
+        }
+        match style.border_top_width() {
+            CssBorderWidthWidth(CssUnitPx(width)) => assert!(css_fixed_to_float(width) == 2.0),
+            _ => fail!(~"border-top-width hint not applied")
+        }
+    }
+
+    #[test]
+    fn computed_values() {
+        use properties::{CssPropZIndex, CssPropOutlineStyle, CssPropTop};
+        use values::{CssComputedZIndex, CssComputedBorderStyle, CssComputedOffset};
+        use values::{CssZIndexSet, CssBorderStyleDashed, CssBorderStyleNone, CssOffsetSet};
+        use values::{CssVerticalAlignMiddle, CssWhiteSpacePre, CssVisibilityHidden, CssListStyleTypeSquare};
+        use values::{CssOverflowHidden, CssDirectionRtl, CssFontVariantSmallCaps};
+        use util::css_fixed_to_float;
+
+        let mut tree = MyTree::new();
+        let html = tree.add(None, "html");
+        let p = tree.add(Some(html), "p");
+
+        let select_ctx = author_select_ctx("p { position: relative; top: 5px; z-index: 3; \
+                                                vertical-align: middle; white-space: pre; \
+                                                visibility: hidden; list-style-type: square; \
+                                                border-top-style: dashed; overflow: hidden; \
+                                                direction: rtl; font-variant: small-caps }", false);
+        let results = select_ctx.select_style(&p, CssMediaScreen, None, &tree);
+        let style = results.computed_style(CssPseudoElementNone);
+
+        match style.vertical_align() {
+            CssVerticalAlignMiddle => (),
+            _ => fail!(~"vertical-align not applied")
+        }
+        assert!(style.white_space() as uint == CssWhiteSpacePre as uint);
+        assert!(style.visibility() as uint == CssVisibilityHidden as uint);
+        assert!(style.list_style_type() as uint == CssListStyleTypeSquare as uint);
+        assert!(style.overflow() as uint == CssOverflowHidden as uint);
+        assert!(style.direction() as uint == CssDirectionRtl as uint);
+        assert!(style.font_variant() as uint == CssFontVariantSmallCaps as uint);
+
+        match style.get(CssPropZIndex) {
+            CssComputedZIndex(CssZIndexSet(3)) => (),
+            _ => fail!(~"z-index not applied")
+        }
+        match style.get(CssPropTop) {
+            CssComputedOffset(CssOffsetSet(CssUnitPx(top))) => assert!(css_fixed_to_float(top) == 5.0),
+            _ => fail!(~"top not applied")
+        }
+        match style.get(CssPropBorderTopStyle) {
+            CssComputedBorderStyle(border) => assert!(border as uint == CssBorderStyleDashed as uint),
+            _ => fail!(~"border-top-style not applied")
+        }
+        match style.get(CssPropOutlineStyle) {
+            CssComputedBorderStyle(outline) => assert!(outline as uint == CssBorderStyleNone as uint),
+            _ => fail!(~"outline-style should be its initial value")
+        }
+    }
+
+    #[test]
+    fn quirks_mode_class_and_id() {
+        let mut tree = MyTree::new();
+        let html = tree.add(None, "html");
+        let body = tree.add(Some(html), "body");
+        let p = tree.add(Some(body), "p");
+        tree.elements[p.index].classes = ~[~"foo"];
+        tree.elements[p.index].id = Some(~"bar");
+
+        let class_css = ".Foo { color: #ff0000 }";
+        assert!(select_color(&author_select_ctx(class_css, false), &tree, p, None) == None);
+        assert!(select_color(&author_select_ctx(class_css, true), &tree, p, None) == Some((255, 0, 0)));
+
+        let id_css = "#Bar { color: #0000ff }";
+        assert!(select_color(&author_select_ctx(id_css, false), &tree, p, None) == None);
+        assert!(select_color(&author_select_ctx(id_css, true), &tree, p, None) == Some((0, 0, 255)));
+    }
+
+    #[test]
+    fn quirks_mode_hashless_colors() {
+        let mut tree = MyTree::new();
+        let html = tree.add(None, "html");
+        let p = tree.add(Some(html), "p");
+
+        let css = "p { color: ff0000 }";
+        assert!(select_color(&author_select_ctx(css, false), &tree, p, None) == None);
+        assert!(select_color(&author_select_ctx(css, true), &tree, p, None) == Some((255, 0, 0)));
+
+        let select_ctx = author_select_ctx("", true);
+        let standards_inline = CssStylesheet::inline("color: 00ff00").unwrap();
+        assert!(select_color(&select_ctx, &tree, p, Some(&standards_inline)) == None);
+        let quirks_inline = CssStylesheet::inline_with_quirks("color: 00ff00", true).unwrap();
+        assert!(select_color(&select_ctx, &tree, p, Some(&quirks_inline)) == Some((0, 255, 0)));
+    }
 }
 
 #[test]
@@ -167,28 +905,322 @@ fn test_arc() {
     use extra::arc::Arc;
     use stylesheet::*;
     use types::CssLevel21;
-    use wapcaplet::LwcString;
-    use super::CssResult;
-
-    let resolve: CssUrlResolutionFn = |a,b| resolve_url(a, b);
-    let params: CssStylesheetParams = CssStylesheetParams {
-        params_version: CssStylesheetParamsVersion1,
-        level: CssLevel21,
-        charset: ~"UTF-8",
-        url: ~"foo",
-        title: ~"foo",
-        allow_quirks: false,
-        inline_style: false,
-        resolve: Some(resolve),
-        import: None,
-        color: None,
-        font: None,
-    };
 
-    let sheet: CssStylesheet = css_stylesheet_create(&params);
+    let params = CssStylesheetParamsBuilder::new()
+        .level(CssLevel21)
+        .url("foo")
+        .title("foo")
+        .build();
+
+    let sheet: CssStylesheet = css_stylesheet_create(params);
     let _arc = Arc::new(sheet);
+}
+
+#[test]
+fn test_shared_sheet() {
+    use extra::arc::Arc;
+    use select::css_select_ctx_create;
+    use stylesheet::CssStylesheet;
+    use types::{CssOriginUA, CssMediaAll};
+
+    let ua_sheet = Arc::new(CssStylesheet::from_str("ua", "h1 { display: block }").unwrap());
+    let mut ctx1 = css_select_ctx_create();
+    let mut ctx2 = css_select_ctx_create();
+    ctx1.append_sheet(ua_sheet.clone(), CssOriginUA, CssMediaAll);
+    ctx2.append_sheet(ua_sheet.clone(), CssOriginUA, CssMediaAll);
+    assert!(ctx1.sheet(0).ll_sheet() == ctx2.sheet(0).ll_sheet());
+
+    // Each ctx can only have the sheet once
+    assert!(ctx1.try_append_sheet(ua_sheet.clone(), CssOriginUA, CssMediaAll).is_err());
+    assert!(ctx1.try_insert_sheet(0, ua_sheet.clone(), CssOriginUA, CssMediaAll).is_err());
+    assert!(ctx1.count_sheets() == 1);
+
+    let removed = ctx1.remove_sheet(0);
+    assert!(removed.get().ll_sheet() == ua_sheet.get().ll_sheet());
+    assert!(ctx2.count_sheets() == 1);
+}
+
+#[test]
+fn test_stylesheet_from_str() {
+    use stylesheet::CssStylesheet;
+
+    let sheet = CssStylesheet::from_str("foo", "h1 { color: red; }").unwrap();
+    assert!(sheet.url() == Some("foo"));
+    assert!(sheet.size() > 0);
+
+    let inline = CssStylesheet::inline("color: red; margin: 0").unwrap();
+    assert!(inline.size() > 0);
+}
+
+#[test]
+fn test_url_resolution() {
+    use extra::arc::RWArc;
+    use stylesheet::{CssStylesheetParamsBuilder, CssUrlResolutionFn, css_stylesheet_create};
+    use wapcaplet::from_rust_string;
+
+    let calls: RWArc<~[(~str, ~str)]> = RWArc::new(~[]);
+    let resolve_calls = calls.clone();
+    // Resolves against the directory of the base url
+    let resolve: CssUrlResolutionFn = |base, rel| {
+        do resolve_calls.write |calls| {
+            calls.push((base.to_owned(), rel.to_str_slice().to_owned()));
+        }
+        let dir = base.slice_to(base.rfind('/').unwrap() + 1);
+        Ok(from_rust_string(fmt!("%s%s", dir, rel.to_str_slice())))
+    };
+
+    let params = CssStylesheetParamsBuilder::new().url("http://example.com/css/main.css").resolve(resolve).build();
+    let mut sheet = css_stylesheet_create(params);
+    assert!(sheet.append_data("@import url(a.css);".as_bytes()).is_ok());
+    assert!(sheet.data_done().is_ok());
+
+    do calls.read |calls| {
+        assert!(*calls == ~[(~"http://example.com/css/main.css", ~"a.css")]);
+    }
+    let import = sheet.next_pending_import().unwrap().unwrap();
+    assert!(import.url.to_str_slice() == "http://example.com/css/a.css");
+}
+
+#[test]
+fn test_imports() {
+    use extra::arc::RWArc;
+    use stylesheet::{CssStylesheet, CssStylesheetParamsBuilder, CssImportNotificationFn, css_stylesheet_create};
+    use stylesheet::{CssParseNeedData, CssParseImportsPending};
+    use types::{CssMediaScreen, CssMediaPrint};
+
+    // The parent's libcss sheet and the url of each import
+    let notifications: RWArc<~[(uint, ~str)]> = RWArc::new(~[]);
+    let import_notifications = notifications.clone();
+    let import: CssImportNotificationFn = |parent, url| {
+        do import_notifications.write |notifications| {
+            notifications.push((parent.ll_sheet() as uint, url.to_str_slice().to_owned()));
+        }
+        Ok(CssMediaScreen | CssMediaPrint)
+    };
+
+    let params = CssStylesheetParamsBuilder::new().url("foo").import(import).build();
+    let mut sheet = css_stylesheet_create(params);
+    match sheet.append_data("@import url(a.css) screen, print; h1 { color: red }".as_bytes()) {
+        Ok(CssParseNeedData) => (),
+        _ => fail!(~"expected to need more data")
+    }
+    match sheet.data_done() {
+        Ok(CssParseImportsPending) => (),
+        _ => fail!(~"expected pending imports")
+    }
+
+    do notifications.read |notifications| {
+        assert!(*notifications == ~[(sheet.ll_sheet() as uint, ~"a.css")]);
+    }
+    // The wrapper the closure was given has been dropped, and the sheet is
+    // still alive
+    assert!(sheet.size() > 0);
+
+    let import = sheet.next_pending_import().unwrap().unwrap();
+    assert!(import.url.to_str_slice() == "a.css");
+    assert!(import.media == CssMediaScreen | CssMediaPrint);
+
+    let child = CssStylesheet::from_str("a.css", "h2 { color: blue }").unwrap();
+    assert!(sheet.register_import(child).is_ok());
+    assert!(sheet.next_pending_import().unwrap().is_none());
+}
+
+#[test]
+fn test_select_ctx_sheets() {
+    use select::css_select_ctx_create;
+    use stylesheet::CssStylesheet;
+    use types::{CssOriginUA, CssOriginAuthor, CssMediaAll, CssMediaPrint};
+
+    let mut select_ctx = css_select_ctx_create();
+    select_ctx.append_sheet(CssStylesheet::from_str("ua", "h1 { display: block }").unwrap(),
+                            CssOriginUA, CssMediaAll);
+    select_ctx.append_sheet(CssStylesheet::from_str("b", "h1 { color: red }").unwrap(),
+                            CssOriginAuthor, CssMediaAll);
+    select_ctx.insert_sheet(1, CssStylesheet::from_str("a", "h1 { color: blue }").unwrap(),
+                            CssOriginAuthor, CssMediaPrint);
+    assert!(select_ctx.count_sheets() == 3);
+    assert!(select_ctx.sheet(1).url() == Some("a"));
+    assert!(select_ctx.sheet_media(1) == CssMediaPrint);
+
+    let replaced = select_ctx.replace_sheet(2, CssStylesheet::from_str("c", "").unwrap());
+    assert!(replaced.get().url() == Some("b"));
+    assert!(select_ctx.sheet(2).url() == Some("c"));
+
+    select_ctx.move_sheet(0, 2);
+    assert!(select_ctx.sheet(0).url() == Some("a"));
+    assert!(select_ctx.sheet(2).url() == Some("ua"));
+    assert!(select_ctx.sheet_origin(2) == CssOriginUA);
+    select_ctx.move_sheet(2, 0);
+    assert!(select_ctx.try_move_sheet(0, 3).is_err());
+
+    let removed = select_ctx.remove_sheet(1);
+    assert!(removed.get().url() == Some("a"));
+    assert!(select_ctx.count_sheets() == 2);
+    assert!(select_ctx.try_remove_sheet(2).is_err());
+    assert!(select_ctx.try_insert_sheet(3, CssStylesheet::from_str("d", "").unwrap(),
+                                        CssOriginAuthor, CssMediaAll).is_err());
+}
+
+#[test]
+fn test_html_select_ctx() {
+    use ua::{UserAgentSheets, html_select_ctx};
+
+    let sheets = UserAgentSheets::new();
+    let standards = html_select_ctx(&sheets, false);
+    assert!(standards.count_sheets() == 1);
+
+    assert!(!standards.quirks_mode());
+
+    let quirks = html_select_ctx(&sheets, true);
+    assert!(quirks.quirks_mode());
+    assert!(quirks.count_sheets() == 2);
+    assert!(quirks.sheet(1).url() == Some("resource:quirks.css"));
+
+    // Both contexts use the sheets parsed by UserAgentSheets::new
+    assert!(standards.sheet(0).ll_sheet() == quirks.sheet(0).ll_sheet());
+    assert!(quirks.sheet(0).ll_sheet() == sheets.html().get().ll_sheet());
+    assert!(quirks.sheet(1).ll_sheet() == sheets.quirks().get().ll_sheet());
+}
+
+#[test]
+fn test_media() {
+    use std::from_str::from_str;
+    use types::*;
+
+    let media: CssMedia = from_str("Screen, print").unwrap();
+    assert!(media == CssMediaScreen | CssMediaPrint);
+    assert!(media.contains(CssMediaPrint));
+    assert!(!media.contains(CssMediaTv));
+    assert_eq!(media.to_str(), ~"print, screen");
+    assert_eq!((media - CssMediaPrint).to_str(), ~"screen");
+
+    let all: CssMedia = from_str("").unwrap();
+    assert!(all == CssMediaAll);
+    assert_eq!(all.to_str(), ~"all");
+
+    let unknown: CssMedia = from_str("3d-glasses").unwrap();
+    assert!(unknown.is_empty());
+
+    let query: Option<CssMedia> = from_str("screen and (color)");
+    assert!(query.is_none());
+}
+
+#[test]
+fn test_error_info() {
+    use errors::{CssError, CssErrorInfo, CssInvalid, CssUnknownError};
+    use ll::errors::{CSS_OK, CSS_INVALID};
+    use ll_result_to_rust_result;
+
+    match CssError::from_ll(CSS_INVALID) {
+        Some(CssInvalid) => (),
+        e => fail!(fmt!("unexpected error: %?", e))
+    }
+    assert!(CssError::from_ll(42).is_none());
+
+    // Unknown codes are errors too, not failures
+    match ll_result_to_rust_result(42, (), "selecting style", None) {
+        Err(info) => {
+            assert!(info.error == CssUnknownError);
+            assert!(info.code == 42);
+            assert_eq!(info.what, ~"selecting style");
+        }
+        Ok(()) => fail!(~"unknown code treated as success")
+    }
+    assert!(ll_result_to_rust_result(CSS_OK, (), "selecting style", None).is_ok());
+
+    let info = CssErrorInfo::new(CSS_INVALID, "appending stylesheet data", Some("foo.css"));
+    assert_eq!(info.to_str(),
+               ~"CSS error while appending stylesheet data (foo.css): invalid input (libcss code 3)");
+}
+
+#[test]
+fn test_attribute_operators() {
+    use util::*;
+
+    assert!(attribute_equal("text", "text"));
+    assert!(!attribute_equal("text", "Text"));
+    assert!(attribute_dashmatch("en", "en"));
+    assert!(attribute_dashmatch("en-US", "en"));
+    assert!(!attribute_dashmatch("english", "en"));
+    assert!(attribute_includes("nav  main\tfooter", "main"));
+    assert!(!attribute_includes("nav main", "na"));
+    assert!(!attribute_includes("nav main", ""));
+    assert!(attribute_prefix("http://example.com", "http"));
+    assert!(attribute_suffix("logo.png", ".png"));
+    assert!(attribute_substring("logo.png", "go.p"));
+    assert!(!attribute_prefix("http://example.com", ""));
+    assert!(!attribute_suffix("logo.png", ""));
+    assert!(!attribute_substring("logo.png", ""));
+}
+
+#[test]
+fn test_lang_matches() {
+    use util::lang_matches;
+
+    assert!(lang_matches("en", "en"));
+    assert!(lang_matches("en-US", "en"));
+    assert!(lang_matches("EN-us", "en-US"));
+    assert!(!lang_matches("eng", "en"));
+    assert!(!lang_matches("en", "en-US"));
+    assert!(!lang_matches("en", ""));
+}
+
+#[test]
+fn test_qname_matches() {
+    use util::{qname_matches, rust_str_to_net_qname, rust_str_to_net_qname_ns};
+    use types::{HTML_NAMESPACE, SVG_NAMESPACE};
+
+    let div = rust_str_to_net_qname("div");
+    assert!(qname_matches(&div, &rust_str_to_net_qname("DIV")));
+    assert!(qname_matches(&div, &rust_str_to_net_qname("*")));
+    assert!(!qname_matches(&div, &rust_str_to_net_qname("span")));
+    assert!(qname_matches(&div, &rust_str_to_net_qname_ns("", "div")));
+    assert!(qname_matches(&rust_str_to_net_qname_ns(HTML_NAMESPACE, "div"),
+                          &rust_str_to_net_qname_ns(HTML_NAMESPACE, "DIV")));
+
+    // svg|rect, *|rect and rect with no default namespace
+    let rect = rust_str_to_net_qname_ns(SVG_NAMESPACE, "rect");
+    assert!(qname_matches(&rect, &rust_str_to_net_qname_ns(SVG_NAMESPACE, "rect")));
+    assert!(qname_matches(&rect, &rust_str_to_net_qname_ns("*", "rect")));
+    assert!(qname_matches(&rect, &rust_str_to_net_qname("rect")));
+    assert!(!qname_matches(&rect, &rust_str_to_net_qname_ns(HTML_NAMESPACE, "rect")));
+    assert!(!qname_matches(&rect, &rust_str_to_net_qname_ns("", "rect")));
+
+    // Names outside HTML are case-sensitive
+    let path = rust_str_to_net_qname_ns(SVG_NAMESPACE, "foreignObject");
+    assert!(!qname_matches(&path, &rust_str_to_net_qname_ns(SVG_NAMESPACE, "foreignobject")));
+}
+
+#[test]
+fn test_write_ll_qname() {
+    use conversions::{write_ll_qname, ll_qname_to_hl_qname};
+    use ll::types::css_qname;
+    use util::{rust_str_to_net_qname, rust_str_to_net_qname_ns, net_qname_ns_to_rust_str};
+    use types::SVG_NAMESPACE;
+    use std::ptr::to_mut_unsafe_ptr;
+
+    let mut svg = rust_str_to_net_qname_ns(SVG_NAMESPACE, "rect");
+    let mut html = rust_str_to_net_qname("div");
+    let mut llqname = css_qname { ns: svg.ns.get_ref().raw_reffed(), name: svg.name.raw_reffed() };
+
+    write_ll_qname(&mut svg, to_mut_unsafe_ptr(&mut llqname));
+    let hlqname = ll_qname_to_hl_qname(&llqname);
+    assert!(net_qname_ns_to_rust_str(&hlqname) == Some(SVG_NAMESPACE));
+
+    // A qname without a namespace must not leave the previous one behind
+    write_ll_qname(&mut html, to_mut_unsafe_ptr(&mut llqname));
+    assert!(llqname.ns.is_null());
+    assert!(ll_qname_to_hl_qname(&llqname).ns.is_none());
+}
+
+#[test]
+fn test_all_properties() {
+    use properties::{all_properties, CSS_N_PROPERTIES};
 
-    fn resolve_url(_base: &str, _rel: &LwcString) -> CssResult<LwcString> {
-        fail!(~"resolving url");
+    let mut n = 0;
+    for property in all_properties() {
+        assert!(property as uint == n);
+        n += 1;
     }
+    assert!(n == CSS_N_PROPERTIES);
 }
@@ -0,0 +1,74 @@
+// Copyright 2013 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/*!
+
+The default user agent stylesheets for HTML documents
+
+*/
+
+use extra::arc::Arc;
+use select::{CssSelectCtx, css_select_ctx_create};
+use stylesheet::CssStylesheet;
+use types::{CssOriginUA, CssMediaAll};
+use require;
+
+static HTML_URL: &'static str = "resource:html.css";
+static HTML_CSS: &'static str = include_str!("html.css");
+
+static QUIRKS_URL: &'static str = "resource:quirks.css";
+static QUIRKS_CSS: &'static str = include_str!("quirks.css");
+
+pub fn html_stylesheet() -> CssStylesheet {
+    require(CssStylesheet::from_str(HTML_URL, HTML_CSS))
+}
+
+// The rules that override html_stylesheet for quirks mode documents
+pub fn quirks_stylesheet() -> CssStylesheet {
+    require(CssStylesheet::from_str(QUIRKS_URL, QUIRKS_CSS))
+}
+
+// The UA stylesheets, parsed once. Create one of these up front and clone
+// it where needed; every select ctx made from it shares the same sheets
+#[deriving(Clone)]
+pub struct UserAgentSheets {
+    priv html: Arc<CssStylesheet>,
+    priv quirks: Arc<CssStylesheet>
+}
+
+impl UserAgentSheets {
+    pub fn new() -> UserAgentSheets {
+        UserAgentSheets {
+            html: Arc::new(html_stylesheet()),
+            quirks: Arc::new(quirks_stylesheet())
+        }
+    }
+
+    pub fn html(&self) -> Arc<CssStylesheet> {
+        self.html.clone()
+    }
+
+    pub fn quirks(&self) -> Arc<CssStylesheet> {
+        self.quirks.clone()
+    }
+}
+
+// Creates a select ctx with the HTML UA stylesheet already appended. In quirks
+// mode the quirks mode UA stylesheet follows it, and the ctx selects in quirks
+// mode. Author sheets for the document should be created with allow_quirks,
+// see CssStylesheet::from_str_with_quirks
+pub fn html_select_ctx(sheets: &UserAgentSheets, quirks: bool) -> CssSelectCtx {
+    let mut select_ctx = css_select_ctx_create();
+    select_ctx.set_quirks_mode(quirks);
+    select_ctx.append_sheet(sheets.html(), CssOriginUA, CssMediaAll);
+    if quirks {
+        select_ctx.append_sheet(sheets.quirks(), CssOriginUA, CssMediaAll);
+    }
+    select_ctx
+}
@@ -8,8 +8,9 @@
 // except according to those terms.
 
 use ll::stylesheet::css_fixed;
-use types::CssQName;
+use types::{CssQName, HTML_NAMESPACE};
 use std::libc::c_void;
+use std::ascii::StrAsciiExt;
 use lwcstr_from_rust_str = wapcaplet::from_rust_string;
 
 pub fn css_fixed_to_float(f: css_fixed) -> float {
@@ -29,10 +30,87 @@ pub fn rust_str_to_net_qname(s: &str) -> CssQName {
     }
 }
 
+pub fn rust_str_to_net_qname_ns(ns: &str, s: &str) -> CssQName {
+    CssQName {
+        ns: Some(lwcstr_from_rust_str(ns)),
+        name: lwcstr_from_rust_str(s)
+    }
+}
+
 pub fn net_qname_to_rust_str<'a>(qname: &'a CssQName) -> &'a str {
     qname.name.to_str_slice()
 }
 
+pub fn net_qname_ns_to_rust_str<'a>(qname: &'a CssQName) -> Option<&'a str> {
+    match qname.ns {
+        Some(ref ns) => Some(ns.to_str_slice()),
+        None => None
+    }
+}
+
+// Type selector matching. A selector namespace of None or "*" matches any
+// namespace, an empty one only elements without a namespace, and a name of
+// "*" any name. Names of HTML elements (and those without a namespace) are
+// compared ASCII case-insensitively, others exactly.
+pub fn qname_matches(name: &CssQName, selector: &CssQName) -> bool {
+    let node_ns = net_qname_ns_to_rust_str(name).unwrap_or("");
+    let ns_matches = match net_qname_ns_to_rust_str(selector) {
+        None | Some("*") => true,
+        Some(ns) => node_ns == ns
+    };
+    if !ns_matches {
+        return false;
+    }
+    let node_name = net_qname_to_rust_str(name);
+    match net_qname_to_rust_str(selector) {
+        "*" => true,
+        sel_name if node_ns.is_empty() || node_ns == HTML_NAMESPACE => node_name.eq_ignore_ascii_case(sel_name),
+        sel_name => node_name == sel_name
+    }
+}
+
+// Attribute selector operators. `attr` is the node's attribute value,
+// `value` the one given in the selector.
+
+pub fn attribute_equal(attr: &str, value: &str) -> bool {
+    attr == value
+}
+
+// [attr|=value]: exactly value, or value immediately followed by '-'
+pub fn attribute_dashmatch(attr: &str, value: &str) -> bool {
+    attr == value || (attr.starts_with(value) && attr.len() > value.len() && attr[value.len()] == '-' as u8)
+}
+
+// [attr~=value]: value is one of a whitespace-separated list of words
+pub fn attribute_includes(attr: &str, value: &str) -> bool {
+    if value.is_empty() || value.iter().any(|c| c.is_whitespace()) {
+        return false;
+    }
+    attr.word_iter().any(|word| word == value)
+}
+
+// The substring operators never match an empty value
+pub fn attribute_prefix(attr: &str, value: &str) -> bool {
+    !value.is_empty() && attr.starts_with(value)
+}
+
+pub fn attribute_suffix(attr: &str, value: &str) -> bool {
+    !value.is_empty() && attr.ends_with(value)
+}
+
+pub fn attribute_substring(attr: &str, value: &str) -> bool {
+    !value.is_empty() && attr.contains(value)
+}
+
+// :lang() matching: the language equals the range, or starts with it
+// followed by '-', ignoring ASCII case (so "en" matches "en-US")
+pub fn lang_matches(lang: &str, range: &str) -> bool {
+    if range.is_empty() || lang.len() < range.len() || !lang.is_char_boundary(range.len()) {
+        return false;
+    }
+    lang.slice_to(range.len()).eq_ignore_ascii_case(range) &&
+        (lang.len() == range.len() || lang[range.len()] == '-' as u8)
+}
 
 // FIXME: These methods should be unsafe
 pub trait VoidPtrLike {